@@ -1,6 +1,9 @@
 use std::fmt;
 use std::str::FromStr;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 #[derive(Debug)]
 pub enum Error {
     InvalidScheme,
@@ -10,6 +13,7 @@ pub enum Error {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct MagnetUri {
     fields: Vec<Field>,
 }
@@ -19,8 +23,136 @@ impl MagnetUri {
         Self { fields }
     }
 
-    pub fn topic(&self) -> Option<Topic> {
-        None
+    /// Returns the first `xt` topic, if any. Magnets can legally repeat
+    /// `xt` (e.g. hybrid v1/v2 torrents); use [`MagnetUri::topics`] to see
+    /// all of them.
+    pub fn topic(&self) -> Option<&Topic> {
+        self.topics().next()
+    }
+
+    /// All `xt` topics, in the order they appeared in the URI.
+    pub fn topics(&self) -> impl Iterator<Item = &Topic> {
+        self.fields.iter().filter_map(|field| match field {
+            Field::ExactTopic(topic) => Some(topic),
+            _ => None,
+        })
+    }
+
+    pub fn display_name(&self) -> Option<&str> {
+        self.fields.iter().find_map(|field| match field {
+            Field::DisplayName(v) => Some(v.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn length(&self) -> Option<u64> {
+        self.fields.iter().find_map(|field| match field {
+            Field::Length(v) => Some(*v),
+            _ => None,
+        })
+    }
+
+    pub fn trackers(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().filter_map(|field| match field {
+            Field::Tracker(v) => Some(v.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn sources(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().filter_map(|field| match field {
+            Field::Source(v) => Some(v.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn keyword_topics(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().filter_map(|field| match field {
+            Field::KeywordTopic(v) => Some(v.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn extensions(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields.iter().filter_map(|field| match field {
+            Field::Extension(k, v) => Some((k.as_str(), v.as_str())),
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for MagnetUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        //  Re-flatten each field back to a key/value pair and let
+        //  serde_urlencoded take care of escaping, mirroring how
+        //  `FromStr` decodes the query string in the first place
+        let pairs = self.fields.iter().map(Field::to_pair).collect::<Vec<_>>();
+
+        let encoded = serde_urlencoded::to_string(&pairs).map_err(|_| fmt::Error)?;
+
+        write!(f, "magnet:?{}", encoded)
+    }
+}
+
+/// Builds a [`MagnetUri`] field by field, for callers that want to emit a
+/// `magnet:?` link instead of parsing one.
+#[derive(Debug, Default)]
+pub struct MagnetUriBuilder {
+    fields: Vec<Field>,
+}
+
+impl MagnetUriBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn exact_topic(mut self, topic: Topic) -> Self {
+        self.fields.push(Field::ExactTopic(topic));
+        self
+    }
+
+    pub fn display_name(mut self, name: &str) -> Self {
+        self.fields.push(Field::DisplayName(name.into()));
+        self
+    }
+
+    pub fn length(mut self, length: u64) -> Self {
+        self.fields.push(Field::Length(length));
+        self
+    }
+
+    pub fn add_tracker(mut self, tracker: &str) -> Self {
+        self.fields.push(Field::Tracker(tracker.into()));
+        self
+    }
+
+    pub fn acceptable_source(mut self, source: &str) -> Self {
+        self.fields.push(Field::AcceptableSource(source.into()));
+        self
+    }
+
+    pub fn source(mut self, source: &str) -> Self {
+        self.fields.push(Field::Source(source.into()));
+        self
+    }
+
+    pub fn keyword_topic(mut self, keyword: &str) -> Self {
+        self.fields.push(Field::KeywordTopic(keyword.into()));
+        self
+    }
+
+    pub fn manifest_topic(mut self, manifest: &str) -> Self {
+        self.fields.push(Field::ManifestTopic(manifest.into()));
+        self
+    }
+
+    pub fn extension(mut self, key: &str, value: &str) -> Self {
+        self.fields.push(Field::Extension(key.into(), value.into()));
+        self
+    }
+
+    pub fn build(self) -> MagnetUri {
+        MagnetUri::from_fields(self.fields)
     }
 }
 
@@ -51,6 +183,7 @@ impl FromStr for MagnetUri {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Field {
     AcceptableSource(String),
     DisplayName(String),
@@ -94,13 +227,231 @@ impl Field {
     pub fn from_pair((key, value): &(String, String)) -> Result<Self, Error> {
         Field::new(key, value)
     }
+
+    //  Flatten a field back into the key/value pair it was parsed from
+    fn to_pair(&self) -> (String, String) {
+        match self {
+            Field::AcceptableSource(v) => ("as".into(), v.clone()),
+            Field::DisplayName(v) => ("dn".into(), v.clone()),
+            Field::Extension(k, v) => (k.clone(), v.clone()),
+            Field::ExactTopic(t) => ("xt".into(), t.to_string()),
+            Field::KeywordTopic(v) => ("kt".into(), v.clone()),
+            Field::Length(v) => ("xl".into(), v.to_string()),
+            Field::ManifestTopic(v) => ("mt".into(), v.clone()),
+            Field::Source(v) => ("xs".into(), v.clone()),
+            Field::Tracker(v) => ("tr".into(), v.clone()),
+            Field::Unknown(k, v) => (k.clone(), v.clone()),
+        }
+    }
+}
+
+/// The 20-byte SHA-1 digest identifying a BitTorrent v1 torrent.
+///
+/// `btih` topics show up in the wild in two encodings -- 40 hex characters
+/// or 32 RFC 4648 base32 characters -- both of which decode to the same
+/// digest. Storing the decoded bytes instead of the raw string gives a
+/// normalized key to compare magnets by, regardless of which encoding the
+/// link happened to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InfoHash([u8; 20]);
+
+//  Serialized as the 40-character hex string rather than a 20-element byte
+//  array, so the structured format stays as human-diffable as the wire one
+#[cfg(feature = "serde")]
+impl Serialize for InfoHash {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for InfoHash {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        InfoHash::from_hex(&s).ok_or_else(|| serde::de::Error::custom("invalid info hash hex"))
+    }
+}
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+impl InfoHash {
+    /// Decodes a 40-character hex `btih` value.
+    pub fn from_hex(s: &str) -> Option<Self> {
+        let bytes = hex_decode(s)?;
+        let bytes: [u8; 20] = bytes.try_into().ok()?;
+        Some(InfoHash(bytes))
+    }
+
+    /// Decodes a 32-character RFC 4648 base32 `btih` value.
+    pub fn from_base32(s: &str) -> Option<Self> {
+        if s.len() != 32 {
+            return None;
+        }
+
+        let mut bits: u64 = 0;
+        let mut bit_count = 0;
+        let mut bytes = Vec::with_capacity(20);
+
+        for c in s.chars() {
+            let value = BASE32_ALPHABET
+                .iter()
+                .position(|&b| b == c.to_ascii_uppercase() as u8)? as u64;
+
+            bits = (bits << 5) | value;
+            bit_count += 5;
+
+            if bit_count >= 8 {
+                bit_count -= 8;
+                bytes.push(((bits >> bit_count) & 0xff) as u8);
+            }
+        }
+
+        let bytes: [u8; 20] = bytes.try_into().ok()?;
+        Some(InfoHash(bytes))
+    }
+
+    /// Renders the digest as 40 lowercase hex characters.
+    pub fn to_hex(&self) -> String {
+        hex_encode(&self.0)
+    }
+
+    /// Renders the digest as 32 uppercase RFC 4648 base32 characters.
+    pub fn to_base32(&self) -> String {
+        let mut bits: u64 = 0;
+        let mut bit_count = 0;
+        let mut out = String::with_capacity(32);
+
+        for &byte in &self.0 {
+            bits = (bits << 8) | byte as u64;
+            bit_count += 8;
+
+            while bit_count >= 5 {
+                bit_count -= 5;
+                out.push(BASE32_ALPHABET[((bits >> bit_count) & 0x1f) as usize] as char);
+            }
+        }
+
+        if bit_count > 0 {
+            out.push(BASE32_ALPHABET[((bits << (5 - bit_count)) & 0x1f) as usize] as char);
+        }
+
+        out
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 20] {
+        &self.0
+    }
+}
+
+impl fmt::Display for InfoHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// A [multihash](https://github.com/multiformats/multihash) digest, used by
+/// `urn:btmh:` topics to carry a BitTorrent v2 info hash. `code` identifies
+/// the hash function (`0x12` for SHA-256) and `digest` is the raw hash
+/// bytes, with no function-specific length enforced here beyond what the
+/// multihash header itself declares.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Multihash {
+    code: u64,
+    digest: Vec<u8>,
+}
+
+impl Multihash {
+    pub fn code(&self) -> u64 {
+        self.code
+    }
+
+    pub fn digest(&self) -> &[u8] {
+        &self.digest
+    }
+}
+
+impl fmt::Display for Multihash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut bytes = Vec::with_capacity(self.digest.len() + 2);
+        write_varint(self.code, &mut bytes);
+        write_varint(self.digest.len() as u64, &mut bytes);
+        bytes.extend_from_slice(&self.digest);
+
+        write!(f, "{}", hex_encode(&bytes))
+    }
+}
+
+//  Reads an unsigned LEB128 varint, as used by the multihash header,
+//  returning the decoded value and the remaining bytes
+fn read_varint(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let mut result: u64 = 0;
+    let mut shift = 0;
+
+    for (i, &byte) in bytes.iter().enumerate() {
+        //  A 10th continuation byte would shift a u64 out of range;
+        //  bail out instead of panicking on crafted input
+        if shift >= 64 {
+            return None;
+        }
+
+        result |= ((byte & 0x7f) as u64) << shift;
+
+        if byte & 0x80 == 0 {
+            return Some((result, &bytes[i + 1..]));
+        }
+
+        shift += 7;
+    }
+
+    None
+}
+
+fn write_varint(mut value: u64, out: &mut Vec<u8>) {
+    loop {
+        let mut byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value != 0 {
+            byte |= 0x80;
+        }
+
+        out.push(byte);
+
+        if value == 0 {
+            break;
+        }
+    }
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Topic {
     AICH(String),
     BitPrint(String),
-    BitTorrent(String),
+    BitTorrent(InfoHash),
+    BitTorrentV2(Multihash),
     ED2K(String),
     Kazaa(String),
     MD5(String),
@@ -109,6 +460,23 @@ pub enum Topic {
     Unknown(String),
 }
 
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Topic::AICH(v) => write!(f, "urn:aich:{}", v),
+            Topic::BitPrint(v) => write!(f, "urn:bitprint:{}", v),
+            Topic::BitTorrent(v) => write!(f, "urn:btih:{}", v),
+            Topic::BitTorrentV2(v) => write!(f, "urn:btmh:{}", v),
+            Topic::ED2K(v) => write!(f, "urn:ed2k:{}", v),
+            Topic::Kazaa(v) => write!(f, "urn:kzhash:{}", v),
+            Topic::MD5(v) => write!(f, "urn:md5:{}", v),
+            Topic::SHA1(v) => write!(f, "urn:sha1:{}", v),
+            Topic::TTHash(v) => write!(f, "urn:tree:tiger:{}", v),
+            Topic::Unknown(v) => write!(f, "{}", v),
+        }
+    }
+}
+
 impl FromStr for Topic {
     type Err = Error;
 
@@ -142,7 +510,44 @@ impl FromStr for Topic {
                 match *key {
                     "aich" => Ok(Topic::AICH(value.to_string())),
                     "bitprint" => Ok(Topic::BitPrint(value.to_string())),
-                    "btih" => Ok(Topic::BitTorrent(value.to_string())),
+                    //  btih shows up in the wild as either 40 hex characters
+                    //  or 32 base32 characters; fall back to Unknown for
+                    //  anything else rather than rejecting the whole magnet.
+                    //  Unknown keeps the whole "urn:btih:..." string so it
+                    //  still round-trips through Display
+                    "btih" => {
+                        if value.len() == 40 && value.bytes().all(|b| b.is_ascii_hexdigit()) {
+                            InfoHash::from_hex(value)
+                                .map(Topic::BitTorrent)
+                                .ok_or_else(|| Error::InvalidTopic(s.into()))
+                        } else if value.len() == 32 {
+                            Ok(InfoHash::from_base32(value)
+                                .map(Topic::BitTorrent)
+                                .unwrap_or_else(|| Topic::Unknown(s.to_string())))
+                        } else {
+                            Ok(Topic::Unknown(s.to_string()))
+                        }
+                    },
+                    //  btmh carries a BitTorrent v2 info hash as a
+                    //  multihash: a varint hash-function code, a varint
+                    //  digest length, then the digest itself
+                    "btmh" => {
+                        let bytes =
+                            hex_decode(value).ok_or_else(|| Error::InvalidTopic(s.into()))?;
+                        let (code, rest) =
+                            read_varint(&bytes).ok_or_else(|| Error::InvalidTopic(s.into()))?;
+                        let (len, digest) =
+                            read_varint(rest).ok_or_else(|| Error::InvalidTopic(s.into()))?;
+
+                        if digest.len() as u64 != len {
+                            return Err(Error::InvalidTopic(s.into()));
+                        }
+
+                        Ok(Topic::BitTorrentV2(Multihash {
+                            code,
+                            digest: digest.to_vec(),
+                        }))
+                    },
                     "ed2k" => Ok(Topic::ED2K(value.to_string())),
                     "kzhash" => Ok(Topic::Kazaa(value.to_string())),
                     "md5" => Ok(Topic::MD5(value.to_string())),
@@ -6,7 +6,131 @@ mod tests {
     #[test]
     fn parse_btih() {
         let uri = MagnetUri::from_str("magnet:?xt=urn:btih:99ab87be389e5487ff626162a5a5988ce696574a&dn=Name&tr=http%3A%2F%example.tracker.com%3A7777%2Fannounce");
-        
+
         assert!(uri.is_ok())
     }
+
+    #[test]
+    fn round_trip_idempotent() {
+        let original = "magnet:?xt=urn:btih:99ab87be389e5487ff626162a5a5988ce696574a&dn=Name&tr=http%3A%2F%2Fexample.tracker.com%3A7777%2Fannounce";
+
+        let parsed = MagnetUri::from_str(original).unwrap();
+        let rendered = parsed.to_string();
+        let reparsed = MagnetUri::from_str(&rendered).unwrap();
+
+        assert_eq!(rendered, reparsed.to_string());
+    }
+
+    #[test]
+    fn builder_round_trip() {
+        let hash = magneturi::InfoHash::from_hex("99ab87be389e5487ff626162a5a5988ce696574a").unwrap();
+        let uri = magneturi::MagnetUriBuilder::new()
+            .exact_topic(magneturi::Topic::BitTorrent(hash))
+            .display_name("Name")
+            .add_tracker("http://example.tracker.com:7777/announce")
+            .build();
+
+        let reparsed = MagnetUri::from_str(&uri.to_string()).unwrap();
+
+        assert_eq!(uri.to_string(), reparsed.to_string());
+    }
+
+    #[test]
+    fn btih_hex_and_base32_agree() {
+        use magneturi::{InfoHash, Topic};
+
+        let hex = Topic::from_str("urn:btih:99ab87be389e5487ff626162a5a5988ce696574a").unwrap();
+        let base32 = Topic::from_str("urn:btih:TGVYPPRYTZKIP73CMFRKLJMYRTTJMV2K").unwrap();
+
+        let (Topic::BitTorrent(hex_hash), Topic::BitTorrent(base32_hash)) = (hex, base32) else {
+            panic!("expected BitTorrent topics");
+        };
+
+        assert_eq!(hex_hash, base32_hash);
+        assert_eq!(hex_hash, InfoHash::from_hex("99ab87be389e5487ff626162a5a5988ce696574a").unwrap());
+    }
+
+    #[test]
+    fn btih_unknown_length_round_trips() {
+        //  A btih value that's neither 40 hex nor 32 base32 characters
+        //  falls back to Topic::Unknown, which must still render as a
+        //  reparseable "urn:btih:..." topic
+        let uri = MagnetUri::from_str("magnet:?xt=urn:btih:abcdef&dn=X").unwrap();
+
+        let rendered = uri.to_string();
+        let reparsed = MagnetUri::from_str(&rendered).unwrap();
+
+        assert_eq!(rendered, reparsed.to_string());
+    }
+
+    #[test]
+    fn parse_btmh_v2_only() {
+        use magneturi::Topic;
+
+        let topic = Topic::from_str(
+            "urn:btmh:1220000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+        )
+        .unwrap();
+
+        let Topic::BitTorrentV2(hash) = topic else {
+            panic!("expected a BitTorrentV2 topic");
+        };
+
+        assert_eq!(hash.code(), 0x12);
+        assert_eq!(hash.digest(), &(0u8..32).collect::<Vec<_>>()[..]);
+    }
+
+    #[test]
+    fn parse_btmh_rejects_oversized_varint() {
+        use magneturi::Topic;
+
+        //  11 continuation bytes would shift a u64 out of range; this must
+        //  be a parse error, not a panic
+        let oversized = format!("urn:btmh:{}00", "80".repeat(11));
+
+        assert!(Topic::from_str(&oversized).is_err());
+    }
+
+    #[test]
+    fn parse_hybrid_v1_and_v2() {
+        let original = "magnet:?xt=urn:btih:99ab87be389e5487ff626162a5a5988ce696574a&xt=urn:btmh:1220000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f&dn=Name";
+
+        let uri = MagnetUri::from_str(original).unwrap();
+
+        //  Both xt fields must survive the round trip -- nothing should
+        //  collapse the repeated key down to just one
+        assert_eq!(uri.topics().count(), 2);
+        assert_eq!(uri.to_string().matches("xt=").count(), 2);
+    }
+
+    #[test]
+    fn query_api() {
+        let uri = MagnetUri::from_str(
+            "magnet:?xt=urn:btih:99ab87be389e5487ff626162a5a5988ce696574a&dn=Name&tr=http%3A%2F%2Fa.tracker%2F&tr=http%3A%2F%2Fb.tracker%2F&xl=1024&x.pe=1.2.3.4",
+        )
+        .unwrap();
+
+        assert!(uri.topic().is_some());
+        assert_eq!(uri.display_name(), Some("Name"));
+        assert_eq!(uri.length(), Some(1024));
+        assert_eq!(
+            uri.trackers().collect::<Vec<_>>(),
+            vec!["http://a.tracker/", "http://b.tracker/"]
+        );
+        assert_eq!(uri.extensions().collect::<Vec<_>>(), vec![("x.pe", "1.2.3.4")]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let uri = MagnetUri::from_str(
+            "magnet:?xt=urn:btih:99ab87be389e5487ff626162a5a5988ce696574a&dn=Name&tr=http%3A%2F%2Fa.tracker%2F",
+        )
+        .unwrap();
+
+        let json = serde_json::to_string(&uri).unwrap();
+        let reparsed: MagnetUri = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(uri.to_string(), reparsed.to_string());
+    }
 }